@@ -9,20 +9,246 @@ use super::{PreciseLocation, RoughLocationID, LocationRequester, LocationRequest
 use itertools::Itertools;
 use super::super::lane::Lane;
 
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Ordering;
+use descartes::P2;
+
+/// A synchronous view onto a lane connectivity graph, sufficient for
+/// `astar_route` to validate a route upfront. Generic over the node type
+/// so the search itself can be unit-tested without standing up real
+/// `LaneID`s; the production implementation is `PathfindingGraph`, backed
+/// by lanes announcing themselves (see `PathfindingGraph::register_lane`)
+/// since `Lane`'s connectivity otherwise only lives behind actor messages.
+pub trait LaneGraph<Node> {
+    fn length(&self, node: Node) -> f32;
+    fn end_position(&self, node: Node) -> P2;
+    fn successors(&self, node: Node) -> CVec<Node>;
+}
+
+#[derive(Copy, Clone)]
+struct Frontier<Node> {
+    node: Node,
+    g: f32,
+    f: OrderedFloat<f32>,
+}
+
+impl<Node> PartialEq for Frontier<Node> {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
+}
+impl<Node> Eq for Frontier<Node> {}
+
+impl<Node> PartialOrd for Frontier<Node> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        // reversed so `BinaryHeap` (a max-heap) pops the *smallest* f first
+        Some(other.f.cmp(&self.f))
+    }
+}
+impl<Node> Ord for Frontier<Node> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.cmp(&self.f)
+    }
+}
+
+/// A* over a lane connectivity graph, bounded by a beam width `B` so
+/// memory stays flat on large maps: whenever the open set grows past `B`
+/// entries after an expansion, the highest-`f` entries are dropped and
+/// only the `B` most promising partial routes survive (as in ED_LRR's
+/// beam search). `g` is lane length accumulated so far, `h` is the
+/// straight-line distance from a lane's end to `goal_position`, which
+/// never overestimates the remaining road distance and so stays
+/// admissible.
+///
+/// Returns `None` if the open set empties before reaching `goal` (e.g.
+/// disconnected components), and resolves instantly to a single-lane
+/// route if `start == goal`.
+pub fn astar_route<Node: Copy + Eq + ::std::hash::Hash>(
+    graph: &impl LaneGraph<Node>,
+    start: Node,
+    goal: Node,
+    goal_position: P2,
+    beam_width: usize,
+) -> Option<CVec<Node>> {
+    if start == goal {
+        return Some(vec![start].into());
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut best_g = HashMap::new();
+    let mut came_from = HashMap::new();
+
+    best_g.insert(start, 0.0);
+    open.push(Frontier {
+        node: start,
+        g: 0.0,
+        f: OrderedFloat(heuristic(graph, start, goal_position)),
+    });
+
+    while let Some(Frontier { node, g, .. }) = open.pop() {
+        if node == goal {
+            return Some(reconstruct_route(&came_from, start, goal));
+        }
+
+        if g > *best_g.get(&node).unwrap_or(&::std::f32::INFINITY) {
+            // a cheaper path to `node` was already found and expanded
+            continue;
+        }
+
+        for successor in graph.successors(node).iter() {
+            let tentative_g = g + graph.length(node);
+
+            if tentative_g < *best_g.get(successor).unwrap_or(&::std::f32::INFINITY) {
+                best_g.insert(*successor, tentative_g);
+                came_from.insert(*successor, node);
+                let f = tentative_g + heuristic(graph, *successor, goal_position);
+                open.push(Frontier { node: *successor, g: tentative_g, f: OrderedFloat(f) });
+            }
+        }
+
+        if open.len() > beam_width {
+            let mut frontier: Vec<_> = open.drain().collect();
+            frontier.sort_by(|a, b| a.f.cmp(&b.f));
+            frontier.truncate(beam_width);
+            open = frontier.into_iter().collect();
+        }
+    }
+
+    None
+}
+
+fn heuristic<Node>(graph: &impl LaneGraph<Node>, node: Node, goal_position: P2) -> f32 {
+    (graph.end_position(node) - goal_position).norm()
+}
+
+fn reconstruct_route<Node: Copy + Eq + ::std::hash::Hash>(
+    came_from: &HashMap<Node, Node>,
+    start: Node,
+    goal: Node,
+) -> CVec<Node> {
+    let mut route = vec![goal];
+    let mut current = goal;
+
+    while current != start {
+        current = came_from[&current];
+        route.push(current);
+    }
+
+    route.reverse();
+    route.into()
+}
+
+pub const DEFAULT_PATHFINDING_BEAM_WIDTH: usize = 200;
+
+#[cfg(test)]
+mod astar_route_tests {
+    use super::*;
+
+    struct FakeLaneGraph {
+        edges: Vec<(u32, u32, f32)>,
+        positions: Vec<(u32, P2)>,
+    }
+
+    impl LaneGraph<u32> for FakeLaneGraph {
+        fn length(&self, node: u32) -> f32 {
+            self.edges.iter().find(|(from, _, _)| *from == node).map_or(0.0, |(_, _, l)| *l)
+        }
+
+        fn end_position(&self, node: u32) -> P2 {
+            self.positions
+                .iter()
+                .find(|(id, _)| *id == node)
+                .map_or_else(|| P2::new(0.0, 0.0), |(_, position)| *position)
+        }
+
+        fn successors(&self, node: u32) -> CVec<u32> {
+            self.edges.iter().filter(|(from, _, _)| *from == node).map(|(_, to, _)| *to).collect()
+        }
+    }
+
+    #[test]
+    fn zero_hop_when_start_equals_goal() {
+        let graph = FakeLaneGraph { edges: vec![], positions: vec![] };
+        let route = astar_route(&graph, 1, 1, P2::new(0.0, 0.0), 10);
+        assert_eq!(route, Some(vec![1].into()));
+    }
+
+    #[test]
+    fn finds_route_through_connected_lanes() {
+        let graph = FakeLaneGraph {
+            edges: vec![(1, 2, 5.0), (2, 3, 5.0)],
+            positions: vec![
+                (1, P2::new(0.0, 0.0)),
+                (2, P2::new(5.0, 0.0)),
+                (3, P2::new(10.0, 0.0)),
+            ],
+        };
+        let route = astar_route(&graph, 1, 3, P2::new(10.0, 0.0), 10);
+        assert_eq!(route, Some(vec![1, 2, 3].into()));
+    }
+
+    #[test]
+    fn none_when_goal_is_disconnected() {
+        let graph = FakeLaneGraph {
+            edges: vec![(1, 2, 5.0)],
+            positions: vec![(1, P2::new(0.0, 0.0)), (2, P2::new(5.0, 0.0))],
+        };
+        let route = astar_route(&graph, 1, 99, P2::new(50.0, 0.0), 10);
+        assert_eq!(route, None);
+    }
+}
+
+/// An opaque reference to a transit line. Nothing in this crate resolves
+/// or drives it: a `Ride` leg completes as soon as its destination
+/// resolves, whatever line it names.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TransitLineID(u32);
+
+/// A single leg of a `Trip`'s `plan`, carrying the rough location the
+/// leg ends at. The leg that is currently being walked/driven/ridden
+/// is tracked by `Trip::current_leg`.
+#[derive(Copy, Clone, Debug)]
+pub enum TripLeg {
+    Walk(RoughLocationID),
+    Drive(RoughLocationID),
+    Ride(TransitLineID, RoughLocationID),
+    Park(RoughLocationID),
+}
+
+impl TripLeg {
+    fn destination(&self) -> RoughLocationID {
+        match *self {
+            TripLeg::Walk(destination) |
+            TripLeg::Drive(destination) |
+            TripLeg::Ride(_, destination) |
+            TripLeg::Park(destination) => destination,
+        }
+    }
+}
+
 #[derive(Compact, Clone)]
 pub struct Trip {
     id: TripID,
     rough_source: RoughLocationID,
     rough_destination: RoughLocationID,
+    plan: CVec<TripLeg>,
+    current_leg: u32,
+    leg_start: RoughLocationID,
+    spawned: Instant,
     source: Option<PreciseLocation>,
     destination: Option<PreciseLocation>,
     listener: Option<TripListenerID>,
+    simulation: SimulationID,
+    max_resolution_retries: u8,
+    retries_remaining: u8,
+    pathfinding_upfront: bool,
+    beam_width: usize,
 }
 
 #[derive(Copy, Clone)]
 pub struct TripResult {
     pub location_now: Option<RoughLocationID>,
-    //pub instant: Instant,
+    pub instant: Instant,
     pub fate: TripFate,
 }
 
@@ -34,37 +260,223 @@ pub enum TripFate {
     RouteForgotten,
     HopDisconnected,
     LaneUnbuilt,
+    /// TODO: never constructed yet — `Park` legs in `location_resolved`
+    /// succeed unconditionally the instant their rough location resolves,
+    /// since there's no parking-capacity model in this tree to exhaust.
+    NoParkingAvailable,
+    /// TODO: never constructed yet — `Ride` legs in `location_resolved`
+    /// succeed unconditionally the instant their rough location resolves,
+    /// since there's no transit-line availability model in this tree to
+    /// fail against.
+    TransitUnavailable,
+    /// Blind-retried resolving its source/destination or waiting for an
+    /// unbuilt lane this many times and still didn't make it, distinct
+    /// from dying on the very first attempt.
+    GaveUpAfterRetries(u8),
     ForceStopped,
 }
 
 const DEBUG_FAILED_TRIPS_VISUALLY: bool = false;
+/// Borrowed from A/B Street's `BLIND_RETRY_TO_SPAWN`: a transient
+/// construction state (e.g. a lane mid-build) shouldn't permanently
+/// discard demand, so failing to resolve is retried this many times
+/// before the trip is allowed to die. This is only the default passed by
+/// callers that don't care to configure it; the actual budget is a
+/// per-trip parameter, see `Trip::max_resolution_retries`.
+pub const DEFAULT_MAX_RESOLUTION_RETRIES: u8 = 3;
+const RESOLUTION_RETRY_DELAY: Ticks = Ticks(20);
 
 impl Trip {
+    /// Spawns a door-to-door `Drive` trip. A convenience wrapper around
+    /// `spawn_with_plan` for the common case; nothing in this tree yet
+    /// decides when a journey should walk to a parked car or ride transit
+    /// instead, so every trip spawned today is a single `Drive` leg.
     pub fn spawn(
         id: TripID,
         rough_source: RoughLocationID,
         rough_destination: RoughLocationID,
         listener: Option<TripListenerID>,
+        simulation: SimulationID,
+        pathfinding_upfront: bool,
+        beam_width: usize,
+        max_resolution_retries: u8,
+        instant: Instant,
+        world: &mut World,
+    ) -> Self {
+        let plan: CVec<TripLeg> = vec![TripLeg::Drive(rough_destination)].into();
+        Self::spawn_with_plan(
+            id,
+            rough_source,
+            rough_destination,
+            plan,
+            listener,
+            simulation,
+            pathfinding_upfront,
+            beam_width,
+            max_resolution_retries,
+            instant,
+            world,
+        )
+    }
+
+    /// Spawns a trip that walks/drives/parks/rides through an explicit
+    /// sequence of legs, executed one at a time via `leg_finished`. This is
+    /// the real entry point for multi-leg journeys (e.g. walk to a parked
+    /// car, drive, park, walk the last stretch); `spawn` just builds the
+    /// single-`Drive`-leg `plan` that's all any caller in this tree
+    /// constructs today, since there's no building/parking/transit
+    /// subsystem yet to decide on a richer one.
+    ///
+    /// Only the `Walk`/`Park`/`Ride` branch of `location_resolved` calls
+    /// `leg_finished`; a `Drive` leg's car arriving at its destination does
+    /// not. A plan with a non-final `Drive` leg spawns the car but never
+    /// advances past it; only single-leg plans and plans whose only `Drive`
+    /// leg is last run end to end.
+    pub fn spawn_with_plan(
+        id: TripID,
+        rough_source: RoughLocationID,
+        rough_destination: RoughLocationID,
+        plan: CVec<TripLeg>,
+        listener: Option<TripListenerID>,
+        simulation: SimulationID,
+        pathfinding_upfront: bool,
+        beam_width: usize,
+        max_resolution_retries: u8,
         instant: Instant,
         world: &mut World,
     ) -> Self {
         rough_source.resolve_as_location(id.into(), rough_source, instant, world);
 
         if let Some(listener) = listener {
-            listener.trip_created(id, world);
+            listener.trip_created(id, rough_source, rough_destination, instant, world);
+            listener.trip_leg_started(id, plan[0], world);
         }
 
         Trip {
             id,
             rough_source,
             rough_destination,
+            plan,
+            current_leg: 0,
+            leg_start: rough_source,
+            spawned: instant,
             listener,
             source: None,
             destination: None,
+            simulation,
+            max_resolution_retries,
+            retries_remaining: max_resolution_retries,
+            pathfinding_upfront,
+            beam_width,
+        }
+    }
+
+    /// Places a car at `source`, driving it towards `destination`. Split
+    /// out of `location_resolved` so the upfront-pathfinding branch can
+    /// share it once a route's been validated.
+    fn spawn_car(
+        &self,
+        source: PreciseLocation,
+        destination: PreciseLocation,
+        instant: Instant,
+        world: &mut World,
+    ) {
+        // TODO: ugly: untyped RawID shenanigans
+        let source_as_lane: LaneLikeID = unsafe { LaneLikeID::from_raw(source.node.as_raw()) };
+        source_as_lane.add_car(
+            LaneCar {
+                trip: self.id,
+                as_obstacle: Obstacle {
+                    position: OrderedFloat(source.offset),
+                    velocity: 0.0,
+                    max_velocity: 8.0,
+                },
+                acceleration: 0.0,
+                destination,
+                next_hop_interaction: None,
+            },
+            None,
+            instant,
+            world,
+        );
+    }
+
+    fn current_leg(&self) -> &TripLeg {
+        &self.plan[self.current_leg as usize]
+    }
+
+    fn is_last_leg(&self) -> bool {
+        self.current_leg as usize + 1 >= self.plan.len()
+    }
+
+    /// Advances the trip once the current leg has arrived at its
+    /// destination: either starts the next leg by resolving the location
+    /// just arrived at as its start, or finishes the trip.
+    ///
+    /// The only caller is the `Walk`/`Park`/`Ride` branch in
+    /// `location_resolved`, which finishes those legs as soon as both ends
+    /// resolve; a `Drive` leg's car arriving does not call this.
+    pub fn leg_finished(&mut self, instant: Instant, world: &mut World) {
+        if self.is_last_leg() {
+            self.id.finish(
+                TripResult {
+                    location_now: Some(self.rough_destination),
+                    instant,
+                    fate: TripFate::Success(instant),
+                },
+                world,
+            );
+        } else {
+            let just_arrived_at = self.current_leg().destination();
+            self.current_leg += 1;
+            self.leg_start = just_arrived_at;
+            self.source = None;
+            self.destination = None;
+
+            if let Some(listener) = self.listener {
+                listener.trip_leg_started(self.id, *self.current_leg(), world);
+            }
+
+            just_arrived_at.resolve_as_location(self.id_as(), just_arrived_at, instant, world);
+        }
+    }
+
+    fn blind_retry(&mut self, result: &TripResult, world: &mut World) -> bool {
+        let is_transient = match result.fate {
+            TripFate::SourceOrDestinationNotResolvable | TripFate::LaneUnbuilt => true,
+            _ => false,
+        };
+
+        if is_transient && self.retries_remaining > 0 {
+            self.retries_remaining -= 1;
+            self.simulation.wake_up_in(RESOLUTION_RETRY_DELAY, self.id_as(), world);
+            true
+        } else {
+            false
         }
     }
 
     pub fn finish(&mut self, result: TripResult, world: &mut World) -> Fate {
+        if self.blind_retry(&result, world) {
+            return Fate::Live;
+        }
+
+        // a trip allowed no retries failed on its first attempt and keeps
+        // its original fate
+        let retries_exhausted = self.max_resolution_retries > 0 &&
+            match result.fate {
+                TripFate::SourceOrDestinationNotResolvable | TripFate::LaneUnbuilt => true,
+                _ => false,
+            };
+        let result = if retries_exhausted {
+            TripResult {
+                fate: TripFate::GaveUpAfterRetries(self.max_resolution_retries),
+                ..result
+            }
+        } else {
+            result
+        };
+
         match result.fate {
             TripFate::Success(_) |
             TripFate::ForceStopped => {}
@@ -90,6 +502,7 @@ impl Trip {
                 result,
                 self.rough_source,
                 self.rough_destination,
+                self.spawned,
                 world,
             );
         }
@@ -98,6 +511,17 @@ impl Trip {
     }
 }
 
+impl Sleeper for Trip {
+    /// Re-attempts resolving the current leg's start location after a
+    /// blind retry delay.
+    fn wake(&mut self, instant: Instant, world: &mut World) {
+        self.source = None;
+        self.destination = None;
+        let leg_start = self.leg_start;
+        leg_start.resolve_as_location(self.id_as(), leg_start, instant, world);
+    }
+}
+
 impl LocationRequester for Trip {
     fn location_resolved(
         &mut self,
@@ -106,46 +530,58 @@ impl LocationRequester for Trip {
         instant: Instant,
         world: &mut World,
     ) {
+        let leg_destination = self.current_leg().destination();
+
         if let Some(precise) = location {
-            if rough_location == self.rough_source {
+            if rough_location == self.leg_start {
                 self.source = Some(precise);
 
-                if self.rough_source == self.rough_destination {
+                if self.leg_start == leg_destination {
                     self.destination = Some(precise);
                 } else {
-                    self.rough_destination.resolve_as_location(
+                    leg_destination.resolve_as_location(
                         self.id_as(),
-                        self.rough_destination,
+                        leg_destination,
                         instant,
                         world,
                     );
                 }
-            } else if rough_location == self.rough_destination {
+            } else if rough_location == leg_destination {
                 self.destination = Some(precise);
             } else {
                 unreachable!();
             }
 
             if let (Some(source), Some(destination)) = (self.source, self.destination) {
-                // TODO: ugly: untyped RawID shenanigans
-                let source_as_lane: LaneLikeID =
-                    unsafe { LaneLikeID::from_raw(source.node.as_raw()) };
-                source_as_lane.add_car(
-                    LaneCar {
-                        trip: self.id,
-                        as_obstacle: Obstacle {
-                            position: OrderedFloat(source.offset),
-                            velocity: 0.0,
-                            max_velocity: 8.0,
-                        },
-                        acceleration: 0.0,
-                        destination,
-                        next_hop_interaction: None,
-                    },
-                    None,
-                    instant,
-                    world,
-                );
+                match *self.current_leg() {
+                    // TODO: seam, not yet wired — nothing calls `leg_finished`
+                    // when this leg's car actually arrives (see `leg_finished`'s
+                    // doc comment), so a `Drive` leg before the last leg of a
+                    // plan will spawn a car but the trip will never advance.
+                    TripLeg::Drive(_) => {
+                        if self.pathfinding_upfront && source.node != destination.node {
+                            PathfindingGraph::local_first(world).find_route(
+                                self.id_as(),
+                                source.node,
+                                destination.node,
+                                self.beam_width,
+                                source,
+                                destination,
+                                instant,
+                                world,
+                            );
+                        } else {
+                            self.spawn_car(source, destination, instant, world);
+                        }
+                    }
+                    // TODO: no walking/parking/transit simulation in this tree yet,
+                    // so these legs complete as soon as their destination resolves —
+                    // `TripFate::NoParkingAvailable`/`TransitUnavailable` can't fire
+                    // until `Park`/`Ride` gain something that can actually fail
+                    TripLeg::Walk(_) | TripLeg::Park(_) | TripLeg::Ride(..) => {
+                        self.id.leg_finished(instant, world);
+                    }
+                }
             }
         } else {
             println!(
@@ -155,6 +591,7 @@ impl LocationRequester for Trip {
             self.id.finish(
                 TripResult {
                     location_now: Some(self.rough_source),
+                    instant,
                     fate: TripFate::SourceOrDestinationNotResolvable,
                 },
                 world,
@@ -163,32 +600,211 @@ impl LocationRequester for Trip {
     }
 }
 
+impl RouteRequester for Trip {
+    /// Resumes upfront pathfinding: spawns the car if `route` validates a
+    /// path through the lane graph, otherwise fails the trip fast with
+    /// `TripFate::NoRoute` instead of handing a car a dead end.
+    fn route_computed(
+        &mut self,
+        route: Option<CVec<LaneID>>,
+        source: PreciseLocation,
+        destination: PreciseLocation,
+        instant: Instant,
+        world: &mut World,
+    ) {
+        match route {
+            // TODO: `LaneCar` has no field to carry a precomputed hop list
+            // in this tree yet, so the validated route itself is discarded
+            // here; the car still navigates hop-by-hop via microtraffic.
+            Some(_) => self.spawn_car(source, destination, instant, world),
+            None => {
+                self.id.finish(
+                    TripResult { location_now: Some(self.leg_start), instant, fate: TripFate::NoRoute },
+                    world,
+                );
+            }
+        }
+    }
+}
+
+/// Caches each lane's routing-relevant data (length, end position and
+/// immediate successors) as lanes announce themselves, and answers
+/// `astar_route` queries against it. Lanes push their data in via
+/// `register_lane` rather than being pulled from synchronously, since a
+/// `Lane`'s own state otherwise only lives behind kay's async actor
+/// messages. `register_lane` is the integration seam for `transport::lane`
+/// to wire up; until a lane registers, routes through it correctly fail
+/// to validate rather than being silently assumed to exist.
+///
+/// NOT YET FUNCTIONAL: nothing in this tree ever calls `register_lane`,
+/// so `nodes` is always empty and `find_route` hands a `None` route to
+/// `route_computed` (and the trip fails with `TripFate::NoRoute`) for
+/// every query with `start != goal`. `Trip::spawn`'s only caller hardcodes `pathfinding_upfront:
+/// false` today for exactly this reason — flip it on only once lanes
+/// actually call `register_lane`.
+#[derive(Compact, Clone)]
+pub struct PathfindingGraph {
+    id: PathfindingGraphID,
+    nodes: CVec<LaneGraphNode>,
+}
+
+#[derive(Compact, Clone)]
+struct LaneGraphNode {
+    lane: LaneID,
+    length: f32,
+    end_position: P2,
+    successors: CVec<LaneID>,
+}
+
+impl PathfindingGraph {
+    pub fn spawn(id: PathfindingGraphID, _: &mut World) -> Self {
+        PathfindingGraph { id, nodes: CVec::new() }
+    }
+
+    pub fn register_lane(
+        &mut self,
+        lane: LaneID,
+        length: f32,
+        end_position: P2,
+        successors: CVec<LaneID>,
+        _world: &mut World,
+    ) {
+        self.nodes = self.nodes.iter().cloned().filter(|node| node.lane != lane).collect();
+        self.nodes.push(LaneGraphNode { lane, length, end_position, successors });
+    }
+
+    pub fn find_route(
+        &mut self,
+        requester: RouteRequesterID,
+        start: LaneID,
+        goal: LaneID,
+        beam_width: usize,
+        source: PreciseLocation,
+        destination: PreciseLocation,
+        instant: Instant,
+        world: &mut World,
+    ) {
+        let goal_position = self.end_position(goal);
+        let route = astar_route(self, start, goal, goal_position, beam_width);
+        requester.route_computed(route, source, destination, instant, world);
+    }
+}
+
+impl LaneGraph<LaneID> for PathfindingGraph {
+    fn length(&self, lane: LaneID) -> f32 {
+        self.nodes.iter().find(|node| node.lane == lane).map_or(0.0, |node| node.length)
+    }
+
+    fn end_position(&self, lane: LaneID) -> P2 {
+        self.nodes
+            .iter()
+            .find(|node| node.lane == lane)
+            .map_or_else(|| P2::new(0.0, 0.0), |node| node.end_position)
+    }
+
+    fn successors(&self, lane: LaneID) -> CVec<LaneID> {
+        self.nodes
+            .iter()
+            .find(|node| node.lane == lane)
+            .map_or_else(CVec::new, |node| node.successors.clone())
+    }
+}
+
 use simulation::{SimulationID, Sleeper, SleeperID};
 use simulation::Ticks;
 use super::super::microtraffic::{LaneLikeID, LaneCar, Obstacle};
 
+/// Callback for an async `PathfindingGraph::find_route` query, mirroring
+/// `LocationRequester`.
+pub trait RouteRequester {
+    fn route_computed(
+        &mut self,
+        route: Option<CVec<LaneID>>,
+        source: PreciseLocation,
+        destination: PreciseLocation,
+        instant: Instant,
+        world: &mut World,
+    );
+}
+
 pub trait TripListener {
-    fn trip_created(&mut self, trip: TripID, world: &mut World);
+    fn trip_created(
+        &mut self,
+        trip: TripID,
+        rough_source: RoughLocationID,
+        rough_destination: RoughLocationID,
+        departure: Instant,
+        world: &mut World,
+    );
+    fn trip_leg_started(&mut self, trip: TripID, leg: TripLeg, world: &mut World);
     fn trip_result(
         &mut self,
         trip: TripID,
         result: TripResult,
         rough_source: RoughLocationID,
         rough_destination: RoughLocationID,
+        spawned: Instant,
         world: &mut World,
     );
 }
 
+/// A single scheduled journey for one simulated person, loaded from a
+/// `Scenario` file rather than invented at runtime.
+#[derive(Compact, Copy, Clone, Serialize, Deserialize)]
+pub struct IndividTrip {
+    pub rough_source: RoughLocationID,
+    pub rough_destination: RoughLocationID,
+    pub departure: Instant,
+}
+
+/// Per-person demand, analogous to A/B Street's `PersonSpec`. Kept as its
+/// own type (rather than flattening to `IndividTrip`) so a person's later
+/// trips of the day can be threaded in here once that's needed.
+#[derive(Compact, Clone, Serialize, Deserialize)]
+pub struct PersonSpec {
+    pub trip: IndividTrip,
+}
+
+/// A whole run's worth of demand, analogous to A/B Street's `Scenario`.
+/// Loading the same file always produces the same `people`, which is what
+/// makes a run reproducible across sessions.
+#[derive(Compact, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub people: CVec<PersonSpec>,
+}
+
+impl Scenario {
+    pub fn load_from_file(path: &str) -> Scenario {
+        let file = ::std::fs::File::open(path).expect("scenario file should exist");
+        ::serde_json::from_reader(file).expect("scenario file should contain a valid Scenario")
+    }
+}
+
 #[derive(Compact, Clone)]
 pub struct TripCreator {
     id: TripCreatorID,
     simulation: SimulationID,
     lanes: CVec<LaneID>,
+    scenario: CVec<IndividTrip>,
+    listener: Option<TripListenerID>,
 }
 
 impl TripCreator {
     pub fn spawn(id: TripCreatorID, simulation: SimulationID, _: &mut World) -> TripCreator {
-        TripCreator { id, simulation, lanes: CVec::new() }
+        TripCreator {
+            id,
+            simulation,
+            lanes: CVec::new(),
+            scenario: CVec::new(),
+            listener: None,
+        }
+    }
+
+    /// Registers the `TripListener` every trip spawned from here on should
+    /// notify (e.g. `Analytics`, `TrafficRecorder`), so those actors aren't
+    /// wired up but unreachable dead code.
+    pub fn set_listener(&mut self, listener: TripListenerID, _world: &mut World) {
+        self.listener = Some(listener);
     }
 
     pub fn add_lane_for_trip(&mut self, lane_id: LaneID, world: &mut World) {
@@ -198,27 +814,81 @@ impl TripCreator {
             self.simulation.wake_up_in(Ticks(50), self.id_as(), world);
         }
     }
+
+    /// Schedules the demand of a deterministic `Scenario`, replacing any
+    /// scenario loaded before: each person's departure is registered with
+    /// the `Sleeper` mechanism and the corresponding trip is spawned exactly
+    /// at its departure tick, so the same scenario file always replays
+    /// the same trips in the same order. Lanes collected through
+    /// `add_lane_for_trip` are still shuffled with `thread_rng` and paired
+    /// into trips in `wake`; loading a scenario does not turn that off.
+    pub fn load_scenario(&mut self, scenario: Scenario, now: Instant, world: &mut World) {
+        self.scenario = scenario.people.iter().map(|person| person.trip).collect();
+        self.schedule_next_departure(now, world);
+    }
+
+    /// Wakes exactly at the next pending departure rather than polling on
+    /// a fixed interval, so a trip spawns on its own tick instead of
+    /// batching onto whichever poll it happens to fall in.
+    fn schedule_next_departure(&mut self, now: Instant, world: &mut World) {
+        if let Some(next_departure) = self.scenario.iter().map(|trip| trip.departure).min() {
+            let delay = if next_departure > now { next_departure - now } else { Ticks(0) };
+            self.simulation.wake_up_in(delay, self.id_as(), world);
+        }
+    }
 }
 
 use rand::Rng;
 
 impl Sleeper for TripCreator {
     fn wake(&mut self, current_instant: Instant, world: &mut World) {
-        ::rand::thread_rng().shuffle(&mut self.lanes);
-
-        for mut pair in &self.lanes.iter().chunks(2) {
-            if let (Some(source), Some(dest)) = (pair.next(), pair.next()) {
-                TripID::spawn(
-                    (*source).into(),
-                    (*dest).into(),
-                    None,
-                    current_instant,
-                    world,
-                );
+        if !self.lanes.is_empty() {
+            ::rand::thread_rng().shuffle(&mut self.lanes);
+
+            for mut pair in &self.lanes.iter().chunks(2) {
+                if let (Some(source), Some(dest)) = (pair.next(), pair.next()) {
+                    // pathfinding_upfront stays false: PathfindingGraph::register_lane
+                    // is never called in this tree, so enabling it would fail every
+                    // trip with TripFate::NoRoute (see PathfindingGraph's doc comment)
+                    TripID::spawn(
+                        (*source).into(),
+                        (*dest).into(),
+                        self.listener,
+                        self.simulation,
+                        false,
+                        DEFAULT_PATHFINDING_BEAM_WIDTH,
+                        DEFAULT_MAX_RESOLUTION_RETRIES,
+                        current_instant,
+                        world,
+                    );
+                }
             }
+
+            self.lanes = CVec::new();
+        }
+
+        let (due, still_pending): (Vec<_>, Vec<_>) = self.scenario
+            .iter()
+            .cloned()
+            .partition(|trip| trip.departure <= current_instant);
+
+        for trip in due {
+            TripID::spawn(
+                trip.rough_source,
+                trip.rough_destination,
+                self.listener,
+                self.simulation,
+                false,
+                DEFAULT_PATHFINDING_BEAM_WIDTH,
+                DEFAULT_MAX_RESOLUTION_RETRIES,
+                current_instant,
+                world,
+            );
         }
 
-        self.lanes = CVec::new();
+        self.scenario = still_pending.into();
+
+        self.schedule_next_departure(current_instant, world);
     }
 }
 
@@ -235,7 +905,7 @@ impl Lane {
 
 use super::{PositionRequester, PositionRequesterID};
 use stagemaster::geometry::{add_debug_line, add_debug_point};
-use descartes::{P2, V2};
+use descartes::V2;
 
 #[derive(Compact, Clone)]
 pub struct FailedTripDebugger {
@@ -299,13 +969,202 @@ impl PositionRequester for FailedTripDebugger {
     }
 }
 
+/// Captures demand passing through a chosen subregion of the map so it can
+/// be replayed in isolation later, analogous to A/B Street's recorder.
+/// `TripCreator` only notifies a single `TripListener`, so recording
+/// requires calling `TripCreator::set_listener` with this actor's id
+/// (swapping out whichever listener, e.g. `Analytics`, was registered
+/// before) for the duration of the capture.
+#[derive(Compact, Clone)]
+pub struct TrafficRecorder {
+    id: TrafficRecorderID,
+    capture_points: CVec<RoughLocationID>,
+    recorded: CVec<IndividTrip>,
+}
+
+impl TrafficRecorder {
+    pub fn spawn(
+        id: TrafficRecorderID,
+        capture_points: CVec<RoughLocationID>,
+        _: &mut World,
+    ) -> TrafficRecorder {
+        TrafficRecorder { id, capture_points, recorded: CVec::new() }
+    }
+
+    fn touches_captured_region(
+        &self,
+        rough_source: RoughLocationID,
+        rough_destination: RoughLocationID,
+    ) -> bool {
+        self.capture_points
+            .iter()
+            .any(|point| *point == rough_source || *point == rough_destination)
+    }
+
+    /// Serializes every trip recorded so far as a `Scenario` that the
+    /// scenario loader can replay, then empties the recording.
+    pub fn stop_recording(&mut self, path: &str, _: &mut World) {
+        let scenario = Scenario {
+            people: self.recorded.iter().cloned().map(|trip| PersonSpec { trip }).collect(),
+        };
+
+        let file = ::std::fs::File::create(path).expect("recording file should be creatable");
+        ::serde_json::to_writer(file, &scenario).expect("recorded trips should serialize");
+
+        self.recorded = CVec::new();
+    }
+}
+
+impl TripListener for TrafficRecorder {
+    fn trip_created(
+        &mut self,
+        _trip: TripID,
+        rough_source: RoughLocationID,
+        rough_destination: RoughLocationID,
+        departure: Instant,
+        _world: &mut World,
+    ) {
+        if self.touches_captured_region(rough_source, rough_destination) {
+            self.recorded.push(IndividTrip { rough_source, rough_destination, departure });
+        }
+    }
+
+    fn trip_leg_started(&mut self, _trip: TripID, _leg: TripLeg, _world: &mut World) {}
+
+    fn trip_result(
+        &mut self,
+        _trip: TripID,
+        _result: TripResult,
+        _rough_source: RoughLocationID,
+        _rough_destination: RoughLocationID,
+        _spawned: Instant,
+        _world: &mut World,
+    ) {
+    }
+}
+
+/// Counts of each `TripFate` plus successful travel durations within one
+/// queried time window, returned to the UI for charting throughput and
+/// failure modes.
+#[derive(Compact, Clone, Default)]
+pub struct WindowedCounts {
+    pub success: u32,
+    pub source_or_destination_not_resolvable: u32,
+    pub no_route: u32,
+    pub route_forgotten: u32,
+    pub hop_disconnected: u32,
+    pub lane_unbuilt: u32,
+    pub no_parking_available: u32,
+    pub transit_unavailable: u32,
+    pub gave_up_after_retries: u32,
+    pub force_stopped: u32,
+    pub success_durations: CVec<Ticks>,
+}
+
+/// One finished trip, kept around long enough to answer windowed queries.
+#[derive(Compact, Copy, Clone)]
+struct TripOutcome {
+    finished_at: Instant,
+    spawned: Instant,
+    fate: TripFate,
+}
+
+/// How long a finished trip's outcome is kept before being evicted.
+/// Nothing queries further back than this, so retaining history beyond
+/// it would just grow `Analytics::outcomes` without bound over a
+/// long-running city.
+const ANALYTICS_RETENTION: Ticks = Ticks(10_000);
+
+/// Aggregates `TripFate`s over time so the UI can chart overall mobility
+/// health instead of relying on per-trip `println!`s, similar to A/B
+/// Street's windowed `Analytics`.
+#[derive(Compact, Clone)]
+pub struct Analytics {
+    id: AnalyticsID,
+    outcomes: CVec<TripOutcome>,
+}
+
+impl Analytics {
+    pub fn spawn(id: AnalyticsID, _: &mut World) -> Analytics {
+        Analytics { id, outcomes: CVec::new() }
+    }
+
+    /// Counts and successful-trip durations for every trip that finished
+    /// at or after `since`.
+    pub fn counts_since(&self, since: Instant) -> WindowedCounts {
+        let mut counts = WindowedCounts::default();
+
+        for outcome in self.outcomes.iter().filter(|outcome| outcome.finished_at >= since) {
+            match outcome.fate {
+                TripFate::Success(_) => {
+                    counts.success += 1;
+                    counts.success_durations.push(outcome.finished_at - outcome.spawned);
+                }
+                TripFate::SourceOrDestinationNotResolvable => {
+                    counts.source_or_destination_not_resolvable += 1;
+                }
+                TripFate::NoRoute => counts.no_route += 1,
+                TripFate::RouteForgotten => counts.route_forgotten += 1,
+                TripFate::HopDisconnected => counts.hop_disconnected += 1,
+                TripFate::LaneUnbuilt => counts.lane_unbuilt += 1,
+                // unreachable today: see `TripFate::NoParkingAvailable`'s doc comment
+                TripFate::NoParkingAvailable => counts.no_parking_available += 1,
+                // unreachable today: see `TripFate::TransitUnavailable`'s doc comment
+                TripFate::TransitUnavailable => counts.transit_unavailable += 1,
+                TripFate::GaveUpAfterRetries(_) => counts.gave_up_after_retries += 1,
+                TripFate::ForceStopped => counts.force_stopped += 1,
+            }
+        }
+
+        counts
+    }
+}
+
+impl TripListener for Analytics {
+    fn trip_created(
+        &mut self,
+        _trip: TripID,
+        _rough_source: RoughLocationID,
+        _rough_destination: RoughLocationID,
+        _departure: Instant,
+        _world: &mut World,
+    ) {
+    }
+
+    fn trip_leg_started(&mut self, _trip: TripID, _leg: TripLeg, _world: &mut World) {}
+
+    fn trip_result(
+        &mut self,
+        _trip: TripID,
+        result: TripResult,
+        _rough_source: RoughLocationID,
+        _rough_destination: RoughLocationID,
+        spawned: Instant,
+        _world: &mut World,
+    ) {
+        let finished_at = result.instant;
+        self.outcomes = self.outcomes
+            .iter()
+            .cloned()
+            .filter(|outcome| finished_at - outcome.finished_at <= ANALYTICS_RETENTION)
+            .collect();
+        self.outcomes.push(TripOutcome { finished_at, spawned, fate: result.fate });
+    }
+}
+
 pub fn setup(system: &mut ActorSystem, simulation: SimulationID) {
     system.register::<Trip>();
     system.register::<TripCreator>();
     system.register::<FailedTripDebugger>();
+    system.register::<TrafficRecorder>();
+    system.register::<Analytics>();
+    system.register::<PathfindingGraph>();
     auto_setup(system);
 
-    TripCreatorID::spawn(simulation, &mut system.world());
+    let trip_creator = TripCreatorID::spawn(simulation, &mut system.world());
+    let analytics = AnalyticsID::spawn(&mut system.world());
+    trip_creator.set_listener(analytics.into(), &mut system.world());
+    PathfindingGraphID::spawn(&mut system.world());
 }
 
 mod kay_auto;